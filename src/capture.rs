@@ -0,0 +1,353 @@
+//! Audio capture backends.
+//!
+//! `Backend::Ffmpeg` shells out to the `ffmpeg` binary, as scribe has always
+//! done. `Backend::Native` records in-process via `cpal` (device
+//! enumeration + streaming) and `hound` (WAV encoding), which avoids the
+//! ffmpeg dependency and lets `--device` match devices by name rather than
+//! an ALSA identifier.
+
+use crate::print_step;
+use crate::vad::{SilenceTracker, HOP_SIZE, WINDOW_SIZE};
+use console::{Style, Term};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+use std::error::Error;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Which capture implementation to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Native,
+    Ffmpeg,
+}
+
+impl Backend {
+    /// Parses a `backend` config/CLI value, defaulting unknown values to ffmpeg.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "native" => Backend::Native,
+            _ => Backend::Ffmpeg,
+        }
+    }
+}
+
+/// Sample rate (Hz) used for the raw PCM stream analyzed by `--auto-stop`.
+const VAD_SAMPLE_RATE: u32 = 16000;
+
+/// Energy multiplier above the noise floor required to classify a frame as speech.
+const VAD_SENSITIVITY: f32 = 3.0;
+
+/// Why the auto-stop wait loop in [`record_ffmpeg`] stopped waiting.
+enum StopSignal {
+    /// The VAD thread classified enough trailing silence as a stop point.
+    SilenceDetected,
+    /// ffmpeg's piped PCM stream ended (e.g. it hit its own `-t` duration
+    /// cap or exited on its own) before silence was ever detected.
+    ChildExited,
+}
+
+/// Parameters shared by every capture backend.
+pub struct RecordingParams {
+    pub device: String,
+    pub duration: u64,
+    pub volume: f32,
+    pub auto_stop: bool,
+    pub silence_timeout: f64,
+    pub output_file: String,
+}
+
+/// Records via the `ffmpeg` CLI, optionally auto-stopping on silence.
+pub fn record_ffmpeg(
+    term: &Term,
+    heading: &Style,
+    params: &RecordingParams,
+) -> Result<(), Box<dyn Error>> {
+    // Spawn ffmpeg for recording with configured parameters. In --auto-stop mode
+    // ffmpeg additionally tees a raw PCM stream to stdout for VAD analysis,
+    // alongside the WAV file it writes for later transcription.
+    let volume_filter = format!("volume={}", params.volume);
+    let duration_str = params.duration.to_string();
+    let mut ffmpeg_args = vec![
+        "-y", // Overwrite output file without prompting.
+        "-f",
+        "alsa",
+        "-i",
+        &params.device,
+        "-filter:a",
+        &volume_filter,
+        "-t",
+        &duration_str,
+        &params.output_file,
+    ];
+    if params.auto_stop {
+        // `-t` is a per-output option, so the pipe needs its own cap too.
+        ffmpeg_args.extend_from_slice(&[
+            "-t",
+            &duration_str,
+            "-f",
+            "s16le",
+            "-ac",
+            "1",
+            "-ar",
+            "16000",
+            "pipe:1",
+        ]);
+    }
+
+    let mut ffmpeg_child = Command::new("ffmpeg")
+        .args(&ffmpeg_args)
+        .stdout(if params.auto_stop {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if params.auto_stop {
+        print_step(
+            term,
+            &format!(
+                "Recording in progress... will stop after {:.1}s of silence.",
+                params.silence_timeout
+            ),
+            heading,
+        )?;
+        let mut stdout = ffmpeg_child.stdout.take().expect("stdout was piped");
+        let (stop_tx, stop_rx) = mpsc::channel::<StopSignal>();
+        let silence_timeout = params.silence_timeout;
+        thread::spawn(move || {
+            let mut tracker = SilenceTracker::new(VAD_SENSITIVITY, silence_timeout, VAD_SAMPLE_RATE);
+            // A sliding buffer of the last WINDOW_SIZE samples, advanced by
+            // HOP_SIZE each iteration, gives 50% overlap between analysis
+            // windows instead of analyzing disjoint back-to-back frames.
+            let mut buffer: Vec<i16> = Vec::with_capacity(WINDOW_SIZE);
+            let mut hop = [0u8; HOP_SIZE * 2];
+            loop {
+                if stdout.read_exact(&mut hop).is_err() {
+                    // ffmpeg closed its end (hit its own `-t` cap, crashed,
+                    // or was killed) before silence was ever detected.
+                    let _ = stop_tx.send(StopSignal::ChildExited);
+                    break;
+                }
+                buffer.extend(
+                    hop.chunks_exact(2)
+                        .map(|b| i16::from_le_bytes([b[0], b[1]])),
+                );
+                if buffer.len() > WINDOW_SIZE {
+                    buffer.drain(..buffer.len() - WINDOW_SIZE);
+                }
+                if buffer.len() < WINDOW_SIZE {
+                    continue;
+                }
+                if tracker.feed(&buffer) {
+                    let _ = stop_tx.send(StopSignal::SilenceDetected);
+                    break;
+                }
+            }
+        });
+        loop {
+            match stop_rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(StopSignal::SilenceDetected) => {
+                    term.clear_line()?;
+                    term.write_line("> Silence detected, stopping recording...")?;
+                    break;
+                }
+                Ok(StopSignal::ChildExited) => {
+                    term.clear_line()?;
+                    term.write_line("> ffmpeg exited before silence was detected, finishing up...")?;
+                    break;
+                }
+                Err(_) => {}
+            }
+            if crate::signal::was_interrupted() {
+                term.clear_line()?;
+                term.write_line("> Interrupted, stopping recording...")?;
+                break;
+            }
+        }
+    } else {
+        print_step(
+            term,
+            "Recording in progress... Press any key to stop.",
+            heading,
+        )?;
+        wait_for_key_or_interrupt(term);
+        term.clear_line()?;
+        term.write_line("> Stopping recording...")?;
+    }
+
+    // Send SIGINT to stop ffmpeg gracefully. The child may have already
+    // exited on its own (its `-t` cap, a crash, or `StopSignal::ChildExited`
+    // above), in which case the signal has no target and that's fine.
+    match kill(Pid::from_raw(ffmpeg_child.id() as i32), Signal::SIGINT) {
+        Ok(()) | Err(nix::Error::ESRCH) => {}
+        Err(err) => return Err(err.into()),
+    }
+    let ffmpeg_exit = ffmpeg_child.wait()?;
+    if let Some(code) = ffmpeg_exit.code() {
+        // Accept both 130 and 255 as graceful SIGINT terminations.
+        if code == 130 || code == 255 {
+            print_step(
+                term,
+                "Recording stopped via SIGINT (desired behavior).",
+                heading,
+            )?;
+        } else if code != 0 {
+            return Err(format!("Failed to record audio. Exit code: {}", code).into());
+        }
+    } else {
+        return Err("ffmpeg terminated without an exit code".into());
+    }
+
+    Ok(())
+}
+
+/// Records natively via `cpal`, writing straight to a WAV file with `hound`.
+pub fn record_native(
+    term: &Term,
+    heading: &Style,
+    params: &RecordingParams,
+) -> Result<(), Box<dyn Error>> {
+    if params.auto_stop {
+        return Err(
+            "--auto-stop requires backend = \"ffmpeg\" (the native backend has no VAD pipeline)"
+                .into(),
+        );
+    }
+
+    let host = cpal::default_host();
+    let device = select_input_device(&host, &params.device)?;
+    let config = device.default_input_config()?;
+    let channels = config.channels();
+    let sample_rate = config.sample_rate().0;
+
+    let spec = WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let writer = WavWriter::create(&params.output_file, spec)?;
+
+    let (tx, rx) = mpsc::channel::<Vec<i16>>();
+    let volume = params.volume;
+    let err_fn = |err| eprintln!("cpal input stream error: {}", err);
+
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::I16 => device.build_input_stream(
+            &config.into(),
+            move |data: &[i16], _| {
+                let scaled = data.iter().map(|&s| scale_i16(s, volume)).collect();
+                let _ = tx.send(scaled);
+            },
+            err_fn,
+            None,
+        )?,
+        cpal::SampleFormat::F32 => device.build_input_stream(
+            &config.into(),
+            move |data: &[f32], _| {
+                let scaled = data.iter().map(|&s| scale_f32(s, volume)).collect();
+                let _ = tx.send(scaled);
+            },
+            err_fn,
+            None,
+        )?,
+        other => return Err(format!("unsupported input sample format: {:?}", other).into()),
+    };
+
+    let writer_handle = thread::spawn(move || {
+        let mut writer = writer;
+        for chunk in rx {
+            for sample in chunk {
+                let _ = writer.write_sample(sample);
+            }
+        }
+        let _ = writer.finalize();
+    });
+
+    stream.play()?;
+    print_step(
+        term,
+        &format!(
+            "Recording in progress (native)... Press any key to stop (auto-stops after {}s).",
+            params.duration
+        ),
+        heading,
+    )?;
+    wait_for_key_interrupt_or_duration(term, Duration::from_secs(params.duration));
+    term.clear_line()?;
+    term.write_line("> Stopping recording...")?;
+    drop(stream);
+
+    writer_handle
+        .join()
+        .map_err(|_| "WAV writer thread panicked")?;
+
+    Ok(())
+}
+
+/// Blocks until the user presses a key or an external SIGINT/SIGTERM is
+/// observed (see [`crate::signal`]), whichever happens first. Polling in
+/// small steps, rather than blocking on `term.read_key()` alone, is what
+/// lets a Ctrl-C during recording still reach the stop path instead of
+/// killing the process (and orphaning the ffmpeg child) outright.
+pub(crate) fn wait_for_key_or_interrupt(term: &Term) {
+    // Recording backends that have their own hard cap (ffmpeg's `-t`) don't
+    // need a duration here; this is effectively "forever" for that purpose.
+    wait_for_key_interrupt_or_duration(term, Duration::from_secs(u64::MAX));
+}
+
+/// Like [`wait_for_key_or_interrupt`], but also returns once `duration` has
+/// elapsed, mirroring the `-t` cap ffmpeg applies to its own recordings.
+pub(crate) fn wait_for_key_interrupt_or_duration(term: &Term, duration: Duration) {
+    let (tx, rx) = mpsc::channel::<()>();
+    let term = term.clone();
+    thread::spawn(move || {
+        let _ = term.read_key();
+        let _ = tx.send(());
+    });
+    let start = Instant::now();
+    loop {
+        if rx.recv_timeout(Duration::from_millis(100)).is_ok() {
+            return;
+        }
+        if crate::signal::was_interrupted() {
+            return;
+        }
+        if start.elapsed() >= duration {
+            return;
+        }
+    }
+}
+
+/// Scales an `i16` sample by `volume`, clamping to avoid wraparound.
+fn scale_i16(sample: i16, volume: f32) -> i16 {
+    (sample as f32 * volume).clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+/// Converts a `f32` sample in `[-1.0, 1.0]` to a volume-scaled `i16`.
+fn scale_f32(sample: f32, volume: f32) -> i16 {
+    (sample * volume * i16::MAX as f32).clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+/// Finds an input device whose name contains `name`, falling back to the
+/// host's default input device (e.g. when `name` is an ALSA identifier that
+/// doesn't correspond to a `cpal` device name).
+pub(crate) fn select_input_device(
+    host: &cpal::Host,
+    name: &str,
+) -> Result<cpal::Device, Box<dyn Error>> {
+    let mut devices = host.input_devices()?;
+    if let Some(device) = devices.find(|d| d.name().map(|n| n.contains(name)).unwrap_or(false)) {
+        return Ok(device);
+    }
+    host.default_input_device()
+        .ok_or_else(|| "no input device available".into())
+}
@@ -0,0 +1,240 @@
+//! `scribe serve`: a long-running daemon that keeps the input device open
+//! and the embedded Whisper model loaded, answering line-delimited JSON
+//! requests over a Unix socket (or stdio) so editor plugins can drive
+//! push-to-talk dictation without paying model/device warm-up on every call.
+
+use crate::capture::select_input_device;
+use crate::transcribe::{resample_linear, EmbeddedModel, WHISPER_SAMPLE_RATE};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+
+/// A request sent to the daemon, one JSON object per line.
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+enum Request {
+    /// Begins capturing. `phrases`, if given, biases recognition toward an
+    /// expected set of words when `stop` later runs transcription.
+    Start {
+        #[serde(default)]
+        phrases: Vec<String>,
+    },
+    /// Ends capturing and returns the transcript.
+    Stop,
+}
+
+#[derive(Serialize)]
+struct SegmentJson {
+    start: f64,
+    end: f64,
+    text: String,
+}
+
+/// A response sent back to the client, one JSON object per line.
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum Response {
+    Recording,
+    Ok {
+        transcript: String,
+        segments: Vec<SegmentJson>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// What's being captured between a `start` and the matching `stop`.
+struct ActiveCapture {
+    samples: Vec<f32>,
+    phrases: Vec<String>,
+}
+
+/// Keeps an input device stream and a Whisper model resident, serving
+/// `start`/`stop` dictation requests with no per-request warm-up.
+pub struct Daemon {
+    model: EmbeddedModel,
+    capture: Arc<Mutex<Option<ActiveCapture>>>,
+    native_rate: u32,
+    // Kept alive for the daemon's lifetime; dropping it would stop capture.
+    _stream: cpal::Stream,
+}
+
+impl Daemon {
+    /// Opens `device_name` and takes the already-loaded `model`, both kept
+    /// resident for the life of the daemon.
+    pub fn new(device_name: &str, model: EmbeddedModel) -> Result<Self, Box<dyn Error>> {
+        let host = cpal::default_host();
+        let device = select_input_device(&host, device_name)?;
+        let config = device.default_input_config()?;
+        let channels = config.channels() as usize;
+        let native_rate = config.sample_rate().0;
+
+        let capture: Arc<Mutex<Option<ActiveCapture>>> = Arc::new(Mutex::new(None));
+        let capture_cb = Arc::clone(&capture);
+        let err_fn = |err| eprintln!("cpal input stream error: {}", err);
+
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::I16 => device.build_input_stream(
+                &config.into(),
+                move |data: &[i16], _| {
+                    if let Some(active) = capture_cb.lock().unwrap().as_mut() {
+                        active.samples.extend(downmix_i16(data, channels));
+                    }
+                },
+                err_fn,
+                None,
+            )?,
+            cpal::SampleFormat::F32 => device.build_input_stream(
+                &config.into(),
+                move |data: &[f32], _| {
+                    if let Some(active) = capture_cb.lock().unwrap().as_mut() {
+                        active.samples.extend(downmix_f32(data, channels));
+                    }
+                },
+                err_fn,
+                None,
+            )?,
+            other => return Err(format!("unsupported input sample format: {:?}", other).into()),
+        };
+        stream.play()?;
+
+        Ok(Self {
+            model,
+            capture,
+            native_rate,
+            _stream: stream,
+        })
+    }
+
+    fn handle(&self, request: Request) -> Response {
+        match request {
+            Request::Start { phrases } => {
+                *self.capture.lock().unwrap() = Some(ActiveCapture {
+                    samples: Vec::new(),
+                    phrases,
+                });
+                Response::Recording
+            }
+            Request::Stop => {
+                let active = self.capture.lock().unwrap().take();
+                let active = match active {
+                    Some(active) => active,
+                    None => {
+                        return Response::Error {
+                            message: "not recording; send \"start\" first".to_string(),
+                        }
+                    }
+                };
+                let resampled =
+                    resample_linear(&active.samples, self.native_rate, WHISPER_SAMPLE_RATE);
+                let result = if active.phrases.is_empty() {
+                    self.model.transcribe_samples(&resampled)
+                } else {
+                    self.model
+                        .transcribe_samples_guided(&resampled, &active.phrases)
+                };
+                match result {
+                    Ok(segments) => {
+                        let transcript = segments
+                            .iter()
+                            .map(|s| s.text.as_str())
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        let segments = segments
+                            .into_iter()
+                            .map(|s| SegmentJson {
+                                start: s.start,
+                                end: s.end,
+                                text: s.text,
+                            })
+                            .collect();
+                        Response::Ok {
+                            transcript,
+                            segments,
+                        }
+                    }
+                    Err(err) => Response::Error {
+                        message: err.to_string(),
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Serves requests over `socket_path` if given, or over stdio otherwise.
+/// Blocks until the client disconnects (stdio) or the process is killed
+/// (socket).
+pub fn serve(daemon: Daemon, socket_path: Option<&str>) -> Result<(), Box<dyn Error>> {
+    match socket_path {
+        Some(path) => serve_socket(daemon, path),
+        None => serve_stdio(daemon),
+    }
+}
+
+fn serve_stdio(daemon: Daemon) -> Result<(), Box<dyn Error>> {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for line in stdin.lock().lines() {
+        respond(&daemon, &line?, &mut out)?;
+    }
+    Ok(())
+}
+
+fn serve_socket(daemon: Daemon, path: &str) -> Result<(), Box<dyn Error>> {
+    // A stale socket file from a previous crashed run would otherwise make bind() fail.
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+    for stream in listener.incoming() {
+        let stream = stream?;
+        if let Err(err) = handle_connection(&daemon, stream) {
+            eprintln!("scribe serve: connection error: {}", err);
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(daemon: &Daemon, stream: UnixStream) -> Result<(), Box<dyn Error>> {
+    let mut writer = stream.try_clone()?;
+    let reader = BufReader::new(stream);
+    for line in reader.lines() {
+        respond(daemon, &line?, &mut writer)?;
+    }
+    Ok(())
+}
+
+fn respond(daemon: &Daemon, line: &str, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    if line.trim().is_empty() {
+        return Ok(());
+    }
+    let response = match serde_json::from_str::<Request>(line) {
+        Ok(request) => daemon.handle(request),
+        Err(err) => Response::Error {
+            message: format!("invalid request: {}", err),
+        },
+    };
+    writeln!(out, "{}", serde_json::to_string(&response)?)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Downmixes interleaved `i16` samples to mono.
+fn downmix_i16(data: &[i16], channels: usize) -> Vec<f32> {
+    data.chunks(channels)
+        .map(|frame| {
+            frame.iter().map(|&s| s as f32 / i16::MAX as f32).sum::<f32>() / channels as f32
+        })
+        .collect()
+}
+
+/// Downmixes interleaved `f32` samples to mono.
+fn downmix_f32(data: &[f32], channels: usize) -> Vec<f32> {
+    data.chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
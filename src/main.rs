@@ -1,13 +1,24 @@
-use clap::Parser;
+mod capture;
+mod daemon;
+mod output;
+mod signal;
+mod stream;
+mod transcribe;
+mod vad;
+
+use capture::{Backend, RecordingParams};
+use clap::{Parser, Subcommand};
 use console::{Style, Term};
-use nix::sys::signal::{kill, Signal};
-use nix::unistd::Pid;
 use serde::Deserialize;
 use std::error::Error;
 use std::fs;
 use std::io::{self, Write};
-use std::process::{Command, Stdio};
+use std::process::{Command as ProcessCommand, Stdio};
 use std::time::{SystemTime, UNIX_EPOCH};
+use transcribe::Engine;
+
+/// Default path to the embedded Whisper ggml model.
+const DEFAULT_MODEL: &str = "ggml-base.en.bin";
 
 /// Application configuration loaded from a TOML file.
 #[derive(Debug, Deserialize)]
@@ -15,6 +26,15 @@ struct Config {
     device: Option<String>,
     duration: Option<u64>,
     volume: Option<f32>,
+    auto_stop: Option<bool>,
+    silence_timeout: Option<f64>,
+    backend: Option<String>,
+    engine: Option<String>,
+    model: Option<String>,
+    stream: Option<bool>,
+    format: Option<String>,
+    output: Option<String>,
+    keep_audio: Option<bool>,
 }
 
 impl Default for Config {
@@ -23,6 +43,15 @@ impl Default for Config {
             device: Some("front:CARD=BRIO".to_string()),
             duration: Some(3600),
             volume: Some(2.0),
+            auto_stop: Some(false),
+            silence_timeout: Some(2.0),
+            backend: Some("ffmpeg".to_string()),
+            engine: Some("cli".to_string()),
+            model: Some(DEFAULT_MODEL.to_string()),
+            stream: Some(false),
+            format: None,
+            output: None,
+            keep_audio: Some(false),
         }
     }
 }
@@ -31,6 +60,9 @@ impl Default for Config {
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to configuration file (TOML format)
     #[arg(short, long, default_value = "~/.config/scribe/config.toml")]
     config: String,
@@ -43,6 +75,45 @@ struct Args {
     /// Audio volume multiplier
     #[arg(long)]
     volume: Option<f32>,
+    /// Stop recording automatically after a silence gap, instead of waiting for a key press
+    #[arg(long)]
+    auto_stop: bool,
+    /// Seconds of silence required to trigger --auto-stop
+    #[arg(long)]
+    silence_timeout: Option<f64>,
+    /// Capture backend: "native" (cpal + hound) or "ffmpeg"
+    #[arg(long)]
+    backend: Option<String>,
+    /// Transcription engine: "embedded" (whisper-rs) or "cli" (the whisper binary)
+    #[arg(long)]
+    engine: Option<String>,
+    /// Path to the ggml model used by the embedded engine
+    #[arg(long)]
+    model: Option<String>,
+    /// Transcribe in sliding windows while recording, instead of only at the end (requires engine = "embedded" and backend = "native")
+    #[arg(long)]
+    stream: bool,
+    /// Transcript output format: "txt", "srt", "vtt", or "json" (default: copy plain text to the clipboard)
+    #[arg(long)]
+    format: Option<String>,
+    /// Write the rendered transcript to this path instead of stdout
+    #[arg(long)]
+    output: Option<String>,
+    /// Keep the recorded WAV file even if the run exits with an error
+    #[arg(long)]
+    keep_audio: bool,
+}
+
+/// Subcommands alongside the default "record once, then transcribe" flow.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Keep the audio device and embedded Whisper model resident, serving
+    /// start/stop dictation requests over a Unix socket or stdio.
+    Serve {
+        /// Unix socket path to listen on (default: read/write JSON over stdio)
+        #[arg(long)]
+        socket: Option<String>,
+    },
 }
 
 /// Load configuration from a file, expanding '~' if necessary.
@@ -63,117 +134,230 @@ fn load_config(path: &str) -> Config {
         .unwrap_or_default()
 }
 
+/// Merged configuration used to drive a single recording+transcription run.
+struct RunConfig {
+    device: String,
+    duration: u64,
+    volume: f32,
+    auto_stop: bool,
+    silence_timeout: f64,
+    backend: Backend,
+    engine: Engine,
+    model: String,
+    stream: bool,
+    format: Option<output::Format>,
+    output: Option<String>,
+    keep_audio: bool,
+}
+
 /// Merge CLI arguments with configuration file values, with CLI taking precedence.
-fn merged_config(args: Args, file_config: Config) -> (String, u64, f32) {
+fn merged_config(args: Args, file_config: Config) -> RunConfig {
     let device = args
         .device
         .or(file_config.device)
         .unwrap_or_else(|| "front:CARD=BRIO".to_string());
     let duration = args.duration.or(file_config.duration).unwrap_or(3600);
     let volume = args.volume.or(file_config.volume).unwrap_or(2.0);
-    (device, duration, volume)
+    let auto_stop = args.auto_stop || file_config.auto_stop.unwrap_or(false);
+    let silence_timeout = args
+        .silence_timeout
+        .or(file_config.silence_timeout)
+        .unwrap_or(2.0);
+    let backend = Backend::parse(
+        &args
+            .backend
+            .or(file_config.backend)
+            .unwrap_or_else(|| "ffmpeg".to_string()),
+    );
+    let engine = Engine::parse(
+        &args
+            .engine
+            .or(file_config.engine)
+            .unwrap_or_else(|| "cli".to_string()),
+    );
+    let model = args
+        .model
+        .or(file_config.model)
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+    let stream = args.stream || file_config.stream.unwrap_or(false);
+    let format = args
+        .format
+        .or(file_config.format)
+        .map(|f| output::Format::parse(&f));
+    let output = args.output.or(file_config.output);
+    let keep_audio = args.keep_audio || file_config.keep_audio.unwrap_or(false);
+    RunConfig {
+        device,
+        duration,
+        volume,
+        auto_stop,
+        silence_timeout,
+        backend,
+        engine,
+        model,
+        stream,
+        format,
+        output,
+        keep_audio,
+    }
 }
 
 /// Clears the current line and prints a styled message starting with a bullet.
-fn print_step(term: &Term, msg: &str, style: &Style) -> io::Result<()> {
+pub(crate) fn print_step(term: &Term, msg: &str, style: &Style) -> io::Result<()> {
     term.clear_line()?;
     term.write_line(&format!("> {}", style.apply_to(msg)))
 }
 
+/// Loads the model and opens the audio device once, then serves dictation
+/// requests until the client disconnects (stdio) or the process is killed
+/// (socket).
+fn run_serve(args: &Args, file_config: Config, socket: Option<String>) -> Result<(), Box<dyn Error>> {
+    let device = args
+        .device
+        .clone()
+        .or(file_config.device)
+        .unwrap_or_else(|| "front:CARD=BRIO".to_string());
+    let model_path = args
+        .model
+        .clone()
+        .or(file_config.model)
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+
+    let term = Term::stdout();
+    let heading = Style::new().bold().cyan();
+
+    print_step(&term, "Loading Whisper model...", &heading)?;
+    let model = transcribe::EmbeddedModel::load(&model_path)?;
+    print_step(&term, "Opening audio device...", &heading)?;
+    let daemon = daemon::Daemon::new(&device, model)?;
+    match &socket {
+        Some(path) => print_step(&term, &format!("Listening on {}...", path), &heading)?,
+        None => print_step(&term, "Listening on stdio...", &heading)?,
+    }
+    daemon::serve(daemon, socket.as_deref())
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     // Parse CLI arguments and load config file.
     let args = Args::parse();
     let file_config = load_config(&args.config);
-    let (device, duration, volume) = merged_config(args, file_config);
 
-    let term = Term::stdout();
-    let heading = Style::new().bold().cyan();
+    if let Some(Command::Serve { socket }) = &args.command {
+        let socket = socket.clone();
+        return run_serve(&args, file_config, socket);
+    }
 
-    print_step(&term, "Starting audio recording...", &heading)?;
+    let config = merged_config(args, file_config);
+    let keep_audio = config.keep_audio;
 
     // Create a temporary filename using a UNIX timestamp.
     let start = SystemTime::now();
     let since_epoch = start.duration_since(UNIX_EPOCH)?.as_secs();
     let output_file = format!("output_{}.wav", since_epoch);
 
-    // Spawn ffmpeg for recording with configured parameters.
-    let mut ffmpeg_child = Command::new("ffmpeg")
-        .args(&[
-            "-y", // Overwrite output file without prompting.
-            "-f",
-            "alsa",
-            "-i",
-            &device,
-            "-filter:a",
-            &format!("volume={}", volume),
-            "-t",
-            &duration.to_string(),
-            &output_file,
-        ])
-        .stderr(Stdio::piped())
-        .spawn()?;
-
-    print_step(
-        &term,
-        "Recording in progress... Press any key to stop.",
-        &heading,
-    )?;
-    // Wait for a single key press.
-    let _ = term.read_key()?;
-    term.clear_line()?;
-    term.write_line("> Stopping recording...")?;
-
-    // Send SIGINT to stop ffmpeg gracefully.
-    kill(Pid::from_raw(ffmpeg_child.id() as i32), Signal::SIGINT)?;
-    let ffmpeg_exit = ffmpeg_child.wait()?;
-    if let Some(code) = ffmpeg_exit.code() {
-        // Accept both 130 and 255 as graceful SIGINT terminations.
-        if code == 130 || code == 255 {
-            print_step(
-                &term,
-                "Recording stopped via SIGINT (desired behavior).",
-                &heading,
-            )?;
-        } else if code != 0 {
-            return Err(format!("Failed to record audio. Exit code: {}", code).into());
+    let result = run(config, &output_file);
+    if result.is_err() && !keep_audio {
+        // Best-effort: don't let a failed cleanup mask the original error.
+        let _ = fs::remove_file(&output_file);
+    }
+    result
+}
+
+/// Runs a single recording+transcription+output pass. Pulled out of `main`
+/// so that any error path can go through one place that decides whether to
+/// delete `output_file` (see `--keep-audio`).
+fn run(config: RunConfig, output_file: &str) -> Result<(), Box<dyn Error>> {
+    let term = Term::stdout();
+    let heading = Style::new().bold().cyan();
+
+    print_step(&term, "Starting audio recording...", &heading)?;
+
+    // Cooperative SIGINT/SIGTERM handling is only polled inside the
+    // recording wait loops below; everything after recording stops
+    // (transcription, the clipboard copy) blocks on calls that never check
+    // the flag, so the default disposition is restored once those loops
+    // exit instead of silently swallowing a later Ctrl-C.
+    signal::install()?;
+
+    let (transcription, segments) = if config.stream {
+        if config.engine != Engine::Embedded {
+            return Err("--stream requires engine = \"embedded\"".into());
+        }
+        if config.backend != Backend::Native {
+            return Err(
+                "--stream requires backend = \"native\" (it records via cpal directly, not ffmpeg)"
+                    .into(),
+            );
         }
+        let model = transcribe::EmbeddedModel::load(&config.model)?;
+        let result = stream::record_and_stream(
+            &term,
+            &heading,
+            &config.device,
+            config.volume,
+            output_file,
+            model,
+        );
+        signal::restore_default()?;
+        result?
     } else {
-        return Err("ffmpeg terminated without an exit code".into());
-    }
+        let recording = RecordingParams {
+            device: config.device,
+            duration: config.duration,
+            volume: config.volume,
+            auto_stop: config.auto_stop,
+            silence_timeout: config.silence_timeout,
+            output_file: output_file.to_string(),
+        };
+        match config.backend {
+            Backend::Native => capture::record_native(&term, &heading, &recording)?,
+            Backend::Ffmpeg => capture::record_ffmpeg(&term, &heading, &recording)?,
+        }
+        signal::restore_default()?;
 
-    print_step(&term, "Transcribing audio...", &heading)?;
-    // Run whisper to transcribe the audio.
-    let whisper_output = Command::new("whisper")
-        .args(&[
-            "--model",
-            "turbo",
-            "--device",
-            "cuda",
-            "--language",
-            "en",
-            &output_file,
-        ])
-        .output()?;
-    if !whisper_output.status.success() {
-        return Err("Whisper transcription failed.".into());
-    }
-    let transcription = String::from_utf8(whisper_output.stdout)?;
+        print_step(&term, "Transcribing audio...", &heading)?;
+        let segments = match config.engine {
+            Engine::Cli => transcribe::transcribe_cli(output_file)?,
+            Engine::Embedded => {
+                let model = transcribe::EmbeddedModel::load(&config.model)?;
+                model.transcribe(&term, &heading, output_file)?
+            }
+        };
+        let transcription = segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        (transcription, segments)
+    };
     print_step(&term, "Transcription complete.", &heading)?;
 
-    // Always copy the transcription to the clipboard.
-    print_step(&term, "Copying transcription to clipboard...", &heading)?;
-    let mut cb_child = Command::new("cb")
-        .arg("copy")
-        .stdin(Stdio::piped())
-        .spawn()?;
-    if let Some(stdin) = cb_child.stdin.as_mut() {
-        stdin.write_all(transcription.as_bytes())?;
-    }
-    let cb_exit = cb_child.wait()?;
-    if !cb_exit.success() {
-        return Err("Failed to copy transcription to clipboard.".into());
+    if config.format.is_none() && config.output.is_none() {
+        // Default behavior, preserved for back-compat: copy plain text to the clipboard.
+        print_step(&term, "Copying transcription to clipboard...", &heading)?;
+        let mut cb_child = ProcessCommand::new("cb")
+            .arg("copy")
+            .stdin(Stdio::piped())
+            .spawn()?;
+        if let Some(stdin) = cb_child.stdin.as_mut() {
+            stdin.write_all(transcription.as_bytes())?;
+        }
+        let cb_exit = cb_child.wait()?;
+        if !cb_exit.success() {
+            return Err("Failed to copy transcription to clipboard.".into());
+        }
+        print_step(&term, "âœ” Copied transcription to clipboard.", &heading)?;
+    } else {
+        let format = config.format.unwrap_or(output::Format::Txt);
+        let rendered = output::render(format, &transcription, &segments)?;
+        match &config.output {
+            Some(path) => {
+                fs::write(path, &rendered)?;
+                print_step(&term, &format!("Wrote transcript to {}", path), &heading)?;
+            }
+            None => println!("{}", rendered),
+        }
     }
-    print_step(&term, "âœ” Copied transcription to clipboard.", &heading)?;
 
     print_step(&term, "Process completed successfully.", &heading)?;
     Ok(())
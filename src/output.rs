@@ -0,0 +1,171 @@
+//! Serializes a transcript as plain text, SRT, WebVTT, or JSON.
+
+use crate::transcribe::Segment;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::Write as _;
+
+/// Supported transcript output formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Txt,
+    Srt,
+    Vtt,
+    Json,
+}
+
+impl Format {
+    /// Parses a `--format` value, defaulting unknown values to plain text.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "srt" => Format::Srt,
+            "vtt" => Format::Vtt,
+            "json" => Format::Json,
+            _ => Format::Txt,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonSegment<'a> {
+    start: f64,
+    end: f64,
+    text: &'a str,
+}
+
+/// Renders a transcript in the requested `format`. `text` is used as-is for
+/// `Format::Txt`; the other formats are built from `segments`' timestamps.
+pub fn render(format: Format, text: &str, segments: &[Segment]) -> Result<String, Box<dyn Error>> {
+    Ok(match format {
+        Format::Txt => text.to_string(),
+        Format::Srt => render_srt(segments),
+        Format::Vtt => render_vtt(segments),
+        Format::Json => {
+            let json_segments: Vec<JsonSegment> = segments
+                .iter()
+                .map(|s| JsonSegment {
+                    start: s.start,
+                    end: s.end,
+                    text: &s.text,
+                })
+                .collect();
+            serde_json::to_string_pretty(&json_segments)?
+        }
+    })
+}
+
+fn render_srt(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    for (i, seg) in segments.iter().enumerate() {
+        let _ = writeln!(out, "{}", i + 1);
+        let _ = writeln!(
+            out,
+            "{} --> {}",
+            format_timestamp(seg.start, ','),
+            format_timestamp(seg.end, ',')
+        );
+        let _ = writeln!(out, "{}", seg.text);
+        let _ = writeln!(out);
+    }
+    out
+}
+
+fn render_vtt(segments: &[Segment]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for seg in segments {
+        let _ = writeln!(
+            out,
+            "{} --> {}",
+            format_timestamp(seg.start, '.'),
+            format_timestamp(seg.end, '.')
+        );
+        let _ = writeln!(out, "{}", seg.text);
+        let _ = writeln!(out);
+    }
+    out
+}
+
+/// Formats `seconds` as `HH:MM:SS<sep>mmm`, as used by both SRT (`,`) and
+/// WebVTT (`.`) cue timestamps.
+fn format_timestamp(seconds: f64, ms_separator: char) -> String {
+    let total_ms = (seconds * 1000.0).round() as i64;
+    let ms = total_ms % 1000;
+    let total_secs = total_ms / 1000;
+    let secs = total_secs % 60;
+    let total_mins = total_secs / 60;
+    let mins = total_mins % 60;
+    let hours = total_mins / 60;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        hours, mins, secs, ms_separator, ms
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments() -> Vec<Segment> {
+        vec![
+            Segment {
+                start: 0.0,
+                end: 1.5,
+                text: "Hello, world!".to_string(),
+            },
+            Segment {
+                start: 3661.25,
+                end: 3662.0,
+                text: "Over an hour in.".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn format_timestamp_uses_the_given_separator() {
+        assert_eq!(format_timestamp(3661.25, ','), "01:01:01,250");
+        assert_eq!(format_timestamp(3661.25, '.'), "01:01:01.250");
+    }
+
+    #[test]
+    fn format_timestamp_rounds_to_the_nearest_millisecond() {
+        assert_eq!(format_timestamp(0.0, ','), "00:00:00,000");
+        assert_eq!(format_timestamp(1.9999, ','), "00:00:02,000");
+    }
+
+    #[test]
+    fn render_srt_numbers_cues_and_formats_timestamps() {
+        let out = render_srt(&segments());
+        assert_eq!(
+            out,
+            "1\n00:00:00,000 --> 00:00:01,500\nHello, world!\n\n\
+             2\n01:01:01,250 --> 01:01:02,000\nOver an hour in.\n\n"
+        );
+    }
+
+    #[test]
+    fn render_vtt_starts_with_the_webvtt_header() {
+        let out = render_vtt(&segments());
+        assert!(out.starts_with("WEBVTT\n\n"));
+        assert!(out.contains("00:00:00.000 --> 00:00:01.500\nHello, world!\n"));
+    }
+
+    #[test]
+    fn render_dispatches_on_format() {
+        let segs = segments();
+        assert_eq!(render(Format::Txt, "plain text", &segs).unwrap(), "plain text");
+        assert_eq!(render(Format::Srt, "", &segs).unwrap(), render_srt(&segs));
+        assert_eq!(render(Format::Vtt, "", &segs).unwrap(), render_vtt(&segs));
+
+        let json = render(Format::Json, "", &segs).unwrap();
+        assert!(json.contains("\"text\": \"Hello, world!\""));
+        assert!(json.contains("\"start\": 0.0"));
+    }
+
+    #[test]
+    fn format_parse_defaults_unknown_values_to_txt() {
+        assert_eq!(Format::parse("srt"), Format::Srt);
+        assert_eq!(Format::parse("vtt"), Format::Vtt);
+        assert_eq!(Format::parse("json"), Format::Json);
+        assert_eq!(Format::parse("anything-else"), Format::Txt);
+    }
+}
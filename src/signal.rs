@@ -0,0 +1,52 @@
+//! A cooperative SIGINT/SIGTERM flag. Handlers only set an atomic flag
+//! rather than terminating the process, so wait loops elsewhere (the
+//! recording stop path, the streaming pipeline) can notice an external
+//! interrupt, forward SIGINT to any ffmpeg child, and still transcribe
+//! whatever was captured instead of leaving an orphaned process behind.
+
+use nix::libc;
+use nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn handle_signal(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Installs handlers for SIGINT and SIGTERM that set a flag instead of
+/// terminating the process. Call once, before entering the recording wait
+/// loops.
+pub fn install() -> Result<(), nix::Error> {
+    let action = SigAction::new(
+        SigHandler::Handler(handle_signal),
+        SaFlags::empty(),
+        SigSet::empty(),
+    );
+    unsafe {
+        signal::sigaction(Signal::SIGINT, &action)?;
+        signal::sigaction(Signal::SIGTERM, &action)?;
+    }
+    Ok(())
+}
+
+/// Returns true once an external SIGINT/SIGTERM has been observed.
+pub fn was_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Restores the default SIGINT/SIGTERM disposition (process terminates
+/// immediately). Call this once the program has left the recording wait
+/// loops that poll [`was_interrupted`] — everything after that point
+/// (transcription, the clipboard copy) blocks on calls that never check the flag, so leaving the handler
+/// installed there would make Ctrl-C/SIGTERM silently do nothing instead
+/// of the ordinary "kill the process" a user expects outside of recording.
+pub fn restore_default() -> Result<(), nix::Error> {
+    let action = SigAction::new(SigHandler::SigDfl, SaFlags::empty(), SigSet::empty());
+    unsafe {
+        signal::sigaction(Signal::SIGINT, &action)?;
+        signal::sigaction(Signal::SIGTERM, &action)?;
+    }
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    Ok(())
+}
@@ -0,0 +1,310 @@
+//! Pipelined "stream" mode: audio is transcribed incrementally while it is
+//! still being recorded, instead of only once recording stops. Raw PCM is
+//! split into overlapping windows and handed to a transcription worker
+//! thread as each window fills, so a live partial transcript appears almost
+//! immediately and the final flush covers whatever is left over.
+
+use crate::capture::{select_input_device, wait_for_key_or_interrupt};
+use crate::print_step;
+use crate::transcribe::{resample_linear, EmbeddedModel, Segment, WHISPER_SAMPLE_RATE};
+use console::{Style, Term};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::error::Error;
+use std::thread;
+
+/// Length of each transcription window, in seconds.
+const WINDOW_SECONDS: f64 = 5.0;
+
+/// Overlap kept between consecutive windows, in seconds.
+const OVERLAP_SECONDS: f64 = 1.0;
+
+/// Records via `cpal` while concurrently transcribing sliding windows of
+/// audio, printing partial transcripts live. Returns the full, deduplicated
+/// transcript and its timestamped segments once recording stops and the
+/// final partial window is flushed.
+pub fn record_and_stream(
+    term: &Term,
+    heading: &Style,
+    device_name: &str,
+    volume: f32,
+    output_file: &str,
+    model: EmbeddedModel,
+) -> Result<(String, Vec<Segment>), Box<dyn Error>> {
+    let host = cpal::default_host();
+    let device = select_input_device(&host, device_name)?;
+    let config = device.default_input_config()?;
+    let channels = config.channels() as usize;
+    let native_rate = config.sample_rate().0;
+
+    let spec = WavSpec {
+        // The aggregator writes the downmixed mono stream, not the device's
+        // interleaved channels.
+        channels: 1,
+        sample_rate: native_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let writer = WavWriter::create(output_file, spec)?;
+
+    // Mono, volume-scaled f32 samples at the device's native rate.
+    let (pcm_tx, pcm_rx) = bounded::<Vec<f32>>(64);
+    let err_fn = |err| eprintln!("cpal input stream error: {}", err);
+
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::I16 => device.build_input_stream(
+            &config.into(),
+            move |data: &[i16], _| {
+                let _ = pcm_tx.send(downmix_i16(data, channels, volume));
+            },
+            err_fn,
+            None,
+        )?,
+        cpal::SampleFormat::F32 => device.build_input_stream(
+            &config.into(),
+            move |data: &[f32], _| {
+                let _ = pcm_tx.send(downmix_f32(data, channels, volume));
+            },
+            err_fn,
+            None,
+        )?,
+        other => return Err(format!("unsupported input sample format: {:?}", other).into()),
+    };
+
+    // 16 kHz mono windows with their start offset in the stream, ready for Whisper.
+    let (window_tx, window_rx) = bounded::<(f64, Vec<f32>)>(8);
+    let writer_handle =
+        thread::spawn(move || write_and_window(pcm_rx, writer, native_rate, window_tx));
+
+    let worker_term = term.clone();
+    let worker_heading = heading.clone();
+    let transcribe_handle =
+        thread::spawn(move || transcription_worker(worker_term, worker_heading, model, window_rx));
+
+    stream.play()?;
+    print_step(
+        term,
+        "Recording in progress (streaming transcription)... Press any key to stop.",
+        heading,
+    )?;
+    wait_for_key_or_interrupt(term);
+    term.clear_line()?;
+    term.write_line("> Stopping recording...")?;
+    // Dropping the stream stops the cpal callback and drops `pcm_tx`, which
+    // ends the aggregator's loop and lets it flush the final partial window.
+    drop(stream);
+
+    writer_handle
+        .join()
+        .map_err(|_| "PCM aggregator thread panicked")??;
+    let (transcript, segments) = transcribe_handle
+        .join()
+        .map_err(|_| "transcription worker thread panicked")?;
+    Ok((transcript, segments))
+}
+
+/// Writes every captured sample to the WAV file and slices the running
+/// buffer into overlapping windows for transcription as it fills, tagging
+/// each window with its start offset (in seconds) within the full stream.
+fn write_and_window(
+    pcm_rx: Receiver<Vec<f32>>,
+    mut writer: WavWriter<std::io::BufWriter<std::fs::File>>,
+    native_rate: u32,
+    window_tx: Sender<(f64, Vec<f32>)>,
+) -> Result<(), Box<dyn Error>> {
+    let window_len = (WINDOW_SECONDS * native_rate as f64) as usize;
+    let overlap_len = (OVERLAP_SECONDS * native_rate as f64) as usize;
+    let mut buffer: Vec<f32> = Vec::new();
+    let mut total_samples_pushed: usize = 0;
+
+    for chunk in pcm_rx.iter() {
+        for &sample in &chunk {
+            writer.write_sample((sample * i16::MAX as f32) as i16)?;
+        }
+        total_samples_pushed += chunk.len();
+        buffer.extend_from_slice(&chunk);
+        while buffer.len() >= window_len {
+            let offset = (total_samples_pushed - buffer.len()) as f64 / native_rate as f64;
+            let window = resample_linear(&buffer[..window_len], native_rate, WHISPER_SAMPLE_RATE);
+            let _ = window_tx.send((offset, window));
+            buffer.drain(..window_len - overlap_len);
+        }
+    }
+    // Flush whatever didn't fill a full window.
+    if !buffer.is_empty() {
+        let offset = (total_samples_pushed - buffer.len()) as f64 / native_rate as f64;
+        let window = resample_linear(&buffer, native_rate, WHISPER_SAMPLE_RATE);
+        let _ = window_tx.send((offset, window));
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Transcribes each incoming window, printing only the words not already
+/// seen in the previous window's overlapping tail, and collects every
+/// window's segments (offset into absolute stream time) for later export.
+fn transcription_worker(
+    term: Term,
+    heading: Style,
+    model: EmbeddedModel,
+    window_rx: Receiver<(f64, Vec<f32>)>,
+) -> (String, Vec<Segment>) {
+    let mut transcript = String::new();
+    let mut all_segments: Vec<Segment> = Vec::new();
+    let mut prev_words: Vec<String> = Vec::new();
+
+    for (offset, window) in window_rx.iter() {
+        let segments = match model.transcribe_samples(&window) {
+            Ok(segments) => segments,
+            Err(err) => {
+                let _ = print_step(&term, &format!("Transcription error: {}", err), &heading);
+                continue;
+            }
+        };
+        let text = segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let words: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+        let overlap = count_overlap(&prev_words, &words);
+        let new_words = &words[overlap..];
+        if !new_words.is_empty() {
+            let line = new_words.join(" ");
+            let _ = print_step(&term, &line, &heading);
+            if !transcript.is_empty() {
+                transcript.push(' ');
+            }
+            transcript.push_str(&line);
+        }
+        all_segments.extend(drop_overlap_segments(segments, overlap).into_iter().map(|s| {
+            Segment {
+                start: s.start + offset,
+                end: s.end + offset,
+                text: s.text,
+            }
+        }));
+        if !words.is_empty() {
+            prev_words = words;
+        }
+    }
+    (transcript, all_segments)
+}
+
+/// Counts how many leading words of `words` already appear at the tail of
+/// `prev_words` (0 if there's no overlap), so callers can drop that many
+/// words from both the printed transcript and the per-window segments.
+fn count_overlap(prev_words: &[String], words: &[String]) -> usize {
+    let max_overlap = words.len().min(prev_words.len());
+    for overlap in (1..=max_overlap).rev() {
+        let prev_tail = &prev_words[prev_words.len() - overlap..];
+        let new_head = &words[..overlap];
+        if prev_tail
+            .iter()
+            .map(|w| w.to_lowercase())
+            .eq(new_head.iter().map(|w| w.to_lowercase()))
+        {
+            return overlap;
+        }
+    }
+    0
+}
+
+/// Drops the first `overlap` words across `segments`' text, discarding
+/// whole segments entirely consumed by the overlap and trimming the one
+/// segment (if any) that only partially overlaps, so timestamped export
+/// doesn't duplicate the overlapping tail of the previous window.
+fn drop_overlap_segments(segments: Vec<Segment>, mut overlap: usize) -> Vec<Segment> {
+    let mut out = Vec::with_capacity(segments.len());
+    for segment in segments {
+        if overlap == 0 {
+            out.push(segment);
+            continue;
+        }
+        let words: Vec<&str> = segment.text.split_whitespace().collect();
+        if overlap >= words.len() {
+            overlap -= words.len();
+            continue;
+        }
+        let text = words[overlap..].join(" ");
+        overlap = 0;
+        out.push(Segment { text, ..segment });
+    }
+    out
+}
+
+/// Downmixes interleaved `i16` samples to mono, applying the volume multiplier.
+fn downmix_i16(data: &[i16], channels: usize, volume: f32) -> Vec<f32> {
+    data.chunks(channels)
+        .map(|frame| {
+            let avg = frame.iter().map(|&s| s as f32).sum::<f32>() / channels as f32;
+            (avg / i16::MAX as f32 * volume).clamp(-1.0, 1.0)
+        })
+        .collect()
+}
+
+/// Downmixes interleaved `f32` samples to mono, applying the volume multiplier.
+fn downmix_f32(data: &[f32], channels: usize, volume: f32) -> Vec<f32> {
+    data.chunks(channels)
+        .map(|frame| {
+            let avg = frame.iter().sum::<f32>() / channels as f32;
+            (avg * volume).clamp(-1.0, 1.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn segment(text: &str) -> Segment {
+        Segment {
+            start: 0.0,
+            end: 1.0,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn count_overlap_finds_the_longest_matching_tail() {
+        let prev = words("the quick brown fox jumps");
+        let next = words("brown Fox jumps over the lazy dog");
+        // "brown fox jumps" (case-insensitively) repeats at the boundary.
+        assert_eq!(count_overlap(&prev, &next), 3);
+    }
+
+    #[test]
+    fn count_overlap_ignores_case_but_not_punctuation() {
+        let prev = words("hello world");
+        let next = words("world, how are you");
+        // "world," != "world" once punctuation is attached, so no overlap.
+        assert_eq!(count_overlap(&prev, &next), 0);
+    }
+
+    #[test]
+    fn count_overlap_is_zero_with_no_history() {
+        assert_eq!(count_overlap(&[], &words("anything at all")), 0);
+    }
+
+    #[test]
+    fn drop_overlap_segments_trims_a_partially_overlapping_segment() {
+        let segments = vec![segment("brown fox jumps over"), segment("the lazy dog")];
+        let trimmed = drop_overlap_segments(segments, 3);
+        assert_eq!(trimmed.len(), 2);
+        assert_eq!(trimmed[0].text, "over");
+        assert_eq!(trimmed[1].text, "the lazy dog");
+    }
+
+    #[test]
+    fn drop_overlap_segments_discards_whole_segments_consumed_by_the_overlap() {
+        let segments = vec![segment("brown fox"), segment("jumps over the lazy dog")];
+        let trimmed = drop_overlap_segments(segments, 3);
+        assert_eq!(trimmed.len(), 1);
+        assert_eq!(trimmed[0].text, "over the lazy dog");
+    }
+}
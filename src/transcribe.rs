@@ -0,0 +1,244 @@
+//! Speech-to-text backends: shelling out to the `whisper` CLI, or running
+//! Whisper in-process via `whisper-rs`.
+
+use console::{Style, Term};
+use hound::WavReader;
+use std::error::Error;
+use std::process::Command;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+use crate::print_step;
+
+/// A transcribed span of audio with its text.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// Which transcription implementation to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    Embedded,
+    Cli,
+}
+
+impl Engine {
+    /// Parses an `engine` config/CLI value, defaulting unknown values to the CLI.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "embedded" => Engine::Embedded,
+            _ => Engine::Cli,
+        }
+    }
+}
+
+/// Transcribes `wav_path` by shelling out to the `whisper` CLI, parsing its
+/// timestamped `[MM:SS.mmm --> MM:SS.mmm]  text` output (with a leading
+/// `HH:` field once past an hour) into segments.
+pub fn transcribe_cli(wav_path: &str) -> Result<Vec<Segment>, Box<dyn Error>> {
+    let output = Command::new("whisper")
+        .args(&[
+            "--model", "turbo", "--device", "cuda", "--language", "en", wav_path,
+        ])
+        .output()?;
+    if !output.status.success() {
+        return Err("Whisper transcription failed.".into());
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+    Ok(parse_cli_segments(&stdout))
+}
+
+fn parse_cli_segments(output: &str) -> Vec<Segment> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix('[')?;
+            let (times, text) = rest.split_once(']')?;
+            let (start_str, end_str) = times.split_once("-->")?;
+            Some(Segment {
+                start: parse_timestamp(start_str.trim())?,
+                end: parse_timestamp(end_str.trim())?,
+                text: text.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parses a `whisper` CLI timestamp into seconds. The CLI omits the hours
+/// field for clips under an hour, printing `MM:SS.mmm` instead of
+/// `HH:MM:SS.mmm`, so fields are read from the right with the hours
+/// defaulting to 0 rather than assumed to always be present.
+fn parse_timestamp(ts: &str) -> Option<f64> {
+    let mut parts = ts.rsplitn(3, ':');
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next().map(|m| m.parse().ok()).unwrap_or(Some(0.0))?;
+    let hours: f64 = parts.next().map(|h| h.parse().ok()).unwrap_or(Some(0.0))?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// A Whisper model loaded once and kept resident for repeated transcriptions.
+pub struct EmbeddedModel {
+    ctx: WhisperContext,
+}
+
+impl EmbeddedModel {
+    /// Loads a ggml model from `model_path` (e.g. `ggml-base.en.bin`).
+    pub fn load(model_path: &str) -> Result<Self, Box<dyn Error>> {
+        let ctx = WhisperContext::new_with_params(model_path, WhisperContextParameters::default())
+            .map_err(|e| format!("failed to load Whisper model '{}': {}", model_path, e))?;
+        Ok(Self { ctx })
+    }
+
+    /// Transcribes `wav_path` in-process, then reports each recognized
+    /// segment via `print_step`. Segments are printed once inference over
+    /// the whole file has finished, not while it is running.
+    pub fn transcribe(
+        &self,
+        term: &Term,
+        heading: &Style,
+        wav_path: &str,
+    ) -> Result<Vec<Segment>, Box<dyn Error>> {
+        let samples = read_wav_as_mono_f32_16k(wav_path)?;
+        let segments = self.transcribe_samples(&samples)?;
+        for segment in &segments {
+            print_step(term, &segment.text, heading)?;
+        }
+        Ok(segments)
+    }
+
+    /// Runs inference directly over 16 kHz mono f32 samples, without any I/O
+    /// or progress reporting. Used both by [`Self::transcribe`] and by the
+    /// streaming pipeline, which feeds it sliding windows of live audio.
+    pub fn transcribe_samples(&self, samples: &[f32]) -> Result<Vec<Segment>, Box<dyn Error>> {
+        self.run_full(samples, None)
+    }
+
+    /// Like [`Self::transcribe_samples`], but biases recognition toward
+    /// `phrases` (e.g. expected commands or vocabulary) via Whisper's
+    /// initial-prompt mechanism.
+    pub fn transcribe_samples_guided(
+        &self,
+        samples: &[f32],
+        phrases: &[String],
+    ) -> Result<Vec<Segment>, Box<dyn Error>> {
+        self.run_full(samples, Some(phrases.join(", ")))
+    }
+
+    fn run_full(
+        &self,
+        samples: &[f32],
+        initial_prompt: Option<String>,
+    ) -> Result<Vec<Segment>, Box<dyn Error>> {
+        let mut state = self.ctx.create_state()?;
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some("en"));
+        params.set_print_progress(false);
+        params.set_print_special(false);
+        params.set_print_realtime(false);
+        if let Some(prompt) = &initial_prompt {
+            params.set_initial_prompt(prompt);
+        }
+        state.full(params, samples)?;
+
+        let num_segments = state.full_n_segments()?;
+        let mut segments = Vec::with_capacity(num_segments as usize);
+        for i in 0..num_segments {
+            let text = state.full_get_segment_text(i)?.trim().to_string();
+            // Whisper reports segment bounds in centiseconds.
+            let start = state.full_get_segment_t0(i)? as f64 * 0.01;
+            let end = state.full_get_segment_t1(i)? as f64 * 0.01;
+            segments.push(Segment { start, end, text });
+        }
+        Ok(segments)
+    }
+}
+
+pub(crate) const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// Reads a WAV file, downmixes to mono, and resamples to 16 kHz f32 samples
+/// as expected by Whisper.
+fn read_wav_as_mono_f32_16k(wav_path: &str) -> Result<Vec<f32>, Box<dyn Error>> {
+    let mut reader = WavReader::open(wav_path)?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+
+    let raw: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Int => reader
+            .samples::<i32>()
+            .map(|s| s.map(|v| v as f32 / i16::MAX as f32))
+            .collect::<Result<_, _>>()?,
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+    };
+
+    let mono: Vec<f32> = if channels > 1 {
+        raw.chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect()
+    } else {
+        raw
+    };
+
+    Ok(resample_linear(&mono, spec.sample_rate, WHISPER_SAMPLE_RATE))
+}
+
+/// Linearly resamples `samples` from `from_rate` to `to_rate` Hz.
+pub(crate) fn resample_linear(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from_rate as f64 / to_rate as f64;
+    let out_len = (samples.len() as f64 / ratio) as usize;
+    (0..out_len)
+        .map(|i| {
+            let src_pos = i as f64 * ratio;
+            let idx = src_pos as usize;
+            let frac = (src_pos - idx as f64) as f32;
+            let a = samples[idx.min(samples.len() - 1)];
+            let b = samples[(idx + 1).min(samples.len() - 1)];
+            a + (b - a) * frac
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_timestamp_reads_full_hh_mm_ss() {
+        assert_eq!(parse_timestamp("01:02:03.500"), Some(3723.5));
+    }
+
+    #[test]
+    fn parse_timestamp_reads_mm_ss_with_no_hours_field() {
+        // The whisper CLI omits the hours field for clips under an hour.
+        assert_eq!(parse_timestamp("02:03.500"), Some(123.5));
+    }
+
+    #[test]
+    fn parse_timestamp_rejects_garbage() {
+        assert_eq!(parse_timestamp("not a timestamp"), None);
+    }
+
+    #[test]
+    fn parse_cli_segments_handles_sub_hour_clips() {
+        let output = "[00:00.000 --> 00:02.500]  Hello, world!\n\
+                       [00:02.500 --> 00:05.000]  How are you?\n";
+        let segments = parse_cli_segments(output);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].start, 0.0);
+        assert_eq!(segments[0].end, 2.5);
+        assert_eq!(segments[0].text, "Hello, world!");
+        assert_eq!(segments[1].text, "How are you?");
+    }
+
+    #[test]
+    fn parse_cli_segments_ignores_unrelated_lines() {
+        let output = "Detecting language...\n[00:00.000 --> 00:01.000]  Hi\n";
+        let segments = parse_cli_segments(output);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].text, "Hi");
+    }
+}
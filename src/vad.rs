@@ -0,0 +1,194 @@
+//! Voice-activity detection based on short-time spectral energy.
+//!
+//! Incoming PCM is split into overlapping windows, each windowed with a Hann
+//! function and run through a real-to-complex FFT. A frame's energy is the
+//! sum of squared magnitudes over its bins. A running noise floor (the 10th
+//! percentile of recent frame energies) lets [`EnergyDetector`] classify each
+//! frame as speech or silence without any fixed amplitude threshold.
+
+use realfft::{num_complex::Complex32, RealFftPlanner, RealToComplex};
+use std::sync::Arc;
+
+/// Number of samples per analysis window.
+pub const WINDOW_SIZE: usize = 512;
+
+/// How far consecutive windows advance, in samples. Half the window size
+/// gives 50% overlap between analysis frames, improving energy resolution
+/// right at speech/silence transitions instead of only sampling them at
+/// hard `WINDOW_SIZE` boundaries.
+pub const HOP_SIZE: usize = WINDOW_SIZE / 2;
+
+/// How many past frame energies are kept when estimating the noise floor.
+const NOISE_HISTORY_LEN: usize = 200;
+
+/// Percentile (0.0-1.0) of recent frame energies treated as the noise floor.
+const NOISE_FLOOR_PERCENTILE: f32 = 0.10;
+
+/// Classifies PCM frames as speech or silence using short-time spectral energy.
+pub struct EnergyDetector {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    sensitivity: f32,
+    energy_history: Vec<f32>,
+    noise_floor: f32,
+    scratch: Vec<Complex32>,
+}
+
+impl EnergyDetector {
+    /// Creates a detector. `sensitivity` is the multiplier applied to the
+    /// noise floor above which a frame is considered speech.
+    pub fn new(sensitivity: f32) -> Self {
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(WINDOW_SIZE);
+        let scratch = fft.make_scratch_vec();
+        Self {
+            fft,
+            window: hann_window(WINDOW_SIZE),
+            sensitivity,
+            energy_history: Vec::new(),
+            noise_floor: 0.0,
+            scratch,
+        }
+    }
+
+    /// Computes the spectral energy of one window of `i16` samples, updates
+    /// the running noise floor, and reports whether the window is speech.
+    ///
+    /// `frame` shorter than [`WINDOW_SIZE`] is zero-padded.
+    pub fn process_frame(&mut self, frame: &[i16]) -> bool {
+        let mut windowed: Vec<f32> = frame
+            .iter()
+            .zip(&self.window)
+            .map(|(&s, &w)| (s as f32 / i16::MAX as f32) * w)
+            .collect();
+        windowed.resize(WINDOW_SIZE, 0.0);
+
+        let mut spectrum = self.fft.make_output_vec();
+        self.fft
+            .process_with_scratch(&mut windowed, &mut spectrum, &mut self.scratch)
+            .expect("FFT input/output buffers are fixed-size and pre-allocated");
+
+        let energy: f32 = spectrum.iter().map(|c| c.norm_sqr()).sum();
+        self.update_noise_floor(energy);
+        energy > self.noise_floor * self.sensitivity
+    }
+
+    fn update_noise_floor(&mut self, energy: f32) {
+        self.energy_history.push(energy);
+        if self.energy_history.len() > NOISE_HISTORY_LEN {
+            self.energy_history.remove(0);
+        }
+        let mut sorted = self.energy_history.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let idx = (((sorted.len() - 1) as f32) * NOISE_FLOOR_PERCENTILE).round() as usize;
+        self.noise_floor = sorted[idx];
+    }
+}
+
+fn hann_window(size: usize) -> Vec<f32> {
+    (0..size)
+        .map(|n| {
+            0.5 - 0.5 * ((2.0 * std::f32::consts::PI * n as f32) / (size as f32 - 1.0)).cos()
+        })
+        .collect()
+}
+
+/// Tracks consecutive non-speech frames and decides when enough silence has
+/// elapsed (after at least one speech frame) to auto-stop a recording.
+pub struct SilenceTracker {
+    detector: EnergyDetector,
+    heard_speech: bool,
+    silent_frames: u32,
+    frames_to_stop: u32,
+}
+
+impl SilenceTracker {
+    /// `silence_timeout` is in seconds; `sample_rate` is the PCM sample rate
+    /// frames are drawn from. Frames are [`WINDOW_SIZE`] samples each, but
+    /// [`Self::feed`] is expected to be called every [`HOP_SIZE`] samples
+    /// (overlapping windows), which is the cadence used here to convert
+    /// `silence_timeout` into a frame count.
+    pub fn new(sensitivity: f32, silence_timeout: f64, sample_rate: u32) -> Self {
+        let frame_seconds = HOP_SIZE as f64 / sample_rate as f64;
+        let frames_to_stop = ((silence_timeout / frame_seconds).ceil() as u32).max(1);
+        Self {
+            detector: EnergyDetector::new(sensitivity),
+            heard_speech: false,
+            silent_frames: 0,
+            frames_to_stop,
+        }
+    }
+
+    /// Feeds one window of samples and returns true once the silence gap has
+    /// been observed following at least one speech frame.
+    pub fn feed(&mut self, frame: &[i16]) -> bool {
+        let is_speech = self.detector.process_frame(frame);
+        if is_speech {
+            self.heard_speech = true;
+            self.silent_frames = 0;
+        } else {
+            self.silent_frames += 1;
+        }
+        self.heard_speech && self.silent_frames >= self.frames_to_stop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn silence() -> Vec<i16> {
+        vec![0i16; WINDOW_SIZE]
+    }
+
+    fn loud_tone() -> Vec<i16> {
+        (0..WINDOW_SIZE)
+            .map(|n| ((n as f32 * 0.2).sin() * i16::MAX as f32 * 0.9) as i16)
+            .collect()
+    }
+
+    #[test]
+    fn hann_window_tapers_to_zero_at_the_edges() {
+        let window = hann_window(WINDOW_SIZE);
+        assert!(window[0] < 1e-6);
+        assert!(window[WINDOW_SIZE - 1] < 1e-6);
+        assert!(window[WINDOW_SIZE / 2] > 0.9);
+    }
+
+    #[test]
+    fn process_frame_accepts_a_short_frame_without_panicking() {
+        let mut detector = EnergyDetector::new(VAD_TEST_SENSITIVITY);
+        let _ = detector.process_frame(&[0, 1, -1]);
+    }
+
+    #[test]
+    fn process_frame_classifies_a_loud_frame_as_speech_once_the_noise_floor_settles() {
+        let mut detector = EnergyDetector::new(VAD_TEST_SENSITIVITY);
+        let silence = silence();
+        for _ in 0..20 {
+            assert!(!detector.process_frame(&silence));
+        }
+        assert!(detector.process_frame(&loud_tone()));
+    }
+
+    #[test]
+    fn silence_tracker_does_not_trigger_without_any_speech() {
+        let mut tracker = SilenceTracker::new(VAD_TEST_SENSITIVITY, 0.05, 16000);
+        let silence = silence();
+        for _ in 0..50 {
+            assert!(!tracker.feed(&silence));
+        }
+    }
+
+    #[test]
+    fn silence_tracker_triggers_after_speech_then_a_silence_gap() {
+        let mut tracker = SilenceTracker::new(VAD_TEST_SENSITIVITY, 0.05, 16000);
+        let silence = silence();
+        for _ in 0..20 {
+            tracker.feed(&silence);
+        }
+        assert!(!tracker.feed(&loud_tone()));
+        assert!((0..50).any(|_| tracker.feed(&silence)));
+    }
+
+    const VAD_TEST_SENSITIVITY: f32 = 3.0;
+}